@@ -115,3 +115,98 @@ pub fn fill_with_generator<T: Copy, G>(target: &mut [T], generator: &mut G, next
         *t = next(generator);
     }
 }
+
+/// Converts a raw 64-bit pseudo-random word produced by [`fill_with_random()`] into a
+/// value of `Self`. Implemented for the built-in integer types, which simply truncate
+/// (or, for the 64-bit types, reinterpret) the generated word.
+pub trait FromRandom {
+    /// Builds a value of `Self` from a raw 64-bit pseudo-random word.
+    fn from_random(word: u64) -> Self;
+}
+
+macro_rules! impl_from_random {
+    ($($t:ty),*) => {
+        $(
+            impl FromRandom for $t {
+                fn from_random(word: u64) -> Self {
+                    word as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_from_random!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// A SplitMix64 generator, used only to turn a `u64` seed into the 4 words of
+/// [`Xoshiro256SS`]'s initial state.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// A small, dependency-free xoshiro256** generator, seeded from a `u64` via
+/// [`SplitMix64`]. Not cryptographically secure: it exists purely to give
+/// [`fill_with_random()`] a fast, reproducible sequence.
+struct Xoshiro256SS {
+    s: [u64; 4],
+}
+
+impl Xoshiro256SS {
+    fn seed_from_u64(seed: u64) -> Self {
+        let mut seeder = SplitMix64(seed);
+        Self {
+            s: [seeder.next(), seeder.next(), seeder.next(), seeder.next()],
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        let result = Self::rotl(self.s[1].wrapping_mul(5), 7).wrapping_mul(9);
+        let t = self.s[1] << 17;
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+        self.s[2] ^= t;
+        self.s[3] = Self::rotl(self.s[3], 45);
+        result
+    }
+
+    fn rotl(x: u64, k: u32) -> u64 {
+        x.rotate_left(k)
+    }
+}
+
+/// Fills the mutable slice with a reproducible pseudo-random sequence derived from
+/// `seed`.
+///
+/// This is driven by a small embedded xoshiro256** generator (seeded with SplitMix64,
+/// the same technique `tempfile` uses for its random name suffixes), so no external
+/// crate is required and a given seed always yields the same sequence, bit-for-bit,
+/// on every platform. That makes a test built from a failing random fixture
+/// replayable: just reuse the seed that was logged.
+///
+/// ```
+/// let mut v: [u8; 6] = [0; 6];
+/// fill_with_random(&mut v, 42);
+/// let mut v2: [u8; 6] = [0; 6];
+/// fill_with_random(&mut v2, 42);
+/// assert_eq!(v, v2);
+/// ```
+///
+/// Arguments:
+/// - `target`: The slice to be filled;
+/// - `seed`: The seed that determines the generated sequence;
+pub fn fill_with_random<T: FromRandom>(target: &mut [T], seed: u64) {
+    let mut rng = Xoshiro256SS::seed_from_u64(seed);
+    for t in target {
+        *t = T::from_random(rng.next());
+    }
+}
@@ -0,0 +1,123 @@
+/*
+ * BSD 3-Clause License
+ *
+ * Copyright (c) 2019-2020, InterlockLedger Network
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright notice, this
+ *   list of conditions and the following disclaimer.
+ *
+ * * Redistributions in binary form must reproduce the above copyright notice,
+ *   this list of conditions and the following disclaimer in the documentation
+ *   and/or other materials provided with the distribution.
+ *
+ * * Neither the name of the copyright holder nor the names of its
+ *   contributors may be used to endorse or promote products derived from
+ *   this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+//! Transparent (de)compression of test fixtures, used by
+//! [`super::TestDirUtils::create_compressed_test_file()`] and
+//! [`super::TestDirUtils::read_compressed_test_file()`] so that large binary test
+//! vectors can be stored compactly instead of as raw bytes.
+use std::io::{Read, Result, Write};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use xz2::read::XzDecoder;
+use xz2::write::XzEncoder;
+
+/// The magic bytes that identify a gzip stream.
+const GZ_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The magic bytes that identify an xz stream.
+const XZ_MAGIC: [u8; 6] = [0xfd, b'7', b'z', b'X', b'Z', 0x00];
+/// The magic bytes that identify a zstd stream.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// The compression formats supported by the compressed test file helpers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionFormat {
+    /// gzip, as produced by `flate2`.
+    Gz,
+    /// xz (LZMA2), as produced by `xz2`.
+    Xz,
+    /// zstd, as produced by the `zstd` crate.
+    Zstd,
+}
+
+impl CompressionFormat {
+    /// Compresses `data` using this format.
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            CompressionFormat::Gz => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            CompressionFormat::Xz => {
+                let mut encoder = XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            CompressionFormat::Zstd => zstd::stream::encode_all(data, 0),
+        }
+    }
+
+    /// Decompresses `data`, which is expected to have been produced by
+    /// [`CompressionFormat::compress()`] with this format.
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            CompressionFormat::Gz => {
+                let mut decompressed = Vec::new();
+                GzDecoder::new(data).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+            CompressionFormat::Xz => {
+                let mut decompressed = Vec::new();
+                XzDecoder::new(data).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+            CompressionFormat::Zstd => zstd::stream::decode_all(data),
+        }
+    }
+
+    /// Returns the format implied by the file extension of `name`, such as `.gz`,
+    /// `.xz` or `.zst`, or `None` if the extension is not recognized.
+    pub fn from_extension(name: &Path) -> Option<Self> {
+        match name.extension().and_then(|e| e.to_str()) {
+            Some("gz") => Some(CompressionFormat::Gz),
+            Some("xz") => Some(CompressionFormat::Xz),
+            Some("zst") => Some(CompressionFormat::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Returns the format implied by the magic bytes at the start of `data`, or
+    /// `None` if none of the supported formats are recognized.
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&GZ_MAGIC) {
+            Some(CompressionFormat::Gz)
+        } else if data.starts_with(&XZ_MAGIC) {
+            Some(CompressionFormat::Xz)
+        } else if data.starts_with(&ZSTD_MAGIC) {
+            Some(CompressionFormat::Zstd)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,156 @@
+/*
+ * BSD 3-Clause License
+ *
+ * Copyright (c) 2019-2020, InterlockLedger Network
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright notice, this
+ *   list of conditions and the following disclaimer.
+ *
+ * * Redistributions in binary form must reproduce the above copyright notice,
+ *   this list of conditions and the following disclaimer in the documentation
+ *   and/or other materials provided with the distribution.
+ *
+ * * Neither the name of the copyright holder nor the names of its
+ *   contributors may be used to endorse or promote products derived from
+ *   this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+//! Cross-process advisory locking of a single fixture file, used to serialize
+//! access to fixtures that are shared across test binaries instead of being owned by
+//! a single [`super::TestDirUtils`] instance.
+use std::fs::{File, OpenOptions};
+use std::io::Result;
+use std::path::Path;
+
+#[cfg(unix)]
+mod imp {
+    use std::fs::File;
+    use std::io::{Error, Result};
+    use std::os::unix::io::AsRawFd;
+
+    const LOCK_EX: i32 = 2;
+    const LOCK_UN: i32 = 8;
+
+    extern "C" {
+        fn flock(fd: i32, operation: i32) -> i32;
+    }
+
+    pub fn lock_exclusive(file: &File) -> Result<()> {
+        if unsafe { flock(file.as_raw_fd(), LOCK_EX) } == 0 {
+            Ok(())
+        } else {
+            Err(Error::last_os_error())
+        }
+    }
+
+    pub fn unlock(file: &File) -> Result<()> {
+        if unsafe { flock(file.as_raw_fd(), LOCK_UN) } == 0 {
+            Ok(())
+        } else {
+            Err(Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::fs::File;
+    use std::io::{Error, Result};
+    use std::os::windows::io::AsRawHandle;
+
+    const LOCKFILE_EXCLUSIVE_LOCK: u32 = 0x2;
+
+    #[repr(C)]
+    struct Overlapped {
+        internal: usize,
+        internal_high: usize,
+        offset: u32,
+        offset_high: u32,
+        h_event: isize,
+    }
+
+    extern "system" {
+        fn LockFileEx(
+            file: isize,
+            flags: u32,
+            reserved: u32,
+            bytes_low: u32,
+            bytes_high: u32,
+            overlapped: *mut Overlapped,
+        ) -> i32;
+        fn UnlockFile(
+            file: isize,
+            offset_low: u32,
+            offset_high: u32,
+            bytes_low: u32,
+            bytes_high: u32,
+        ) -> i32;
+    }
+
+    pub fn lock_exclusive(file: &File) -> Result<()> {
+        let mut overlapped: Overlapped = unsafe { std::mem::zeroed() };
+        let handle = file.as_raw_handle() as isize;
+        if unsafe { LockFileEx(handle, LOCKFILE_EXCLUSIVE_LOCK, 0, u32::MAX, u32::MAX, &mut overlapped) } != 0 {
+            Ok(())
+        } else {
+            Err(Error::last_os_error())
+        }
+    }
+
+    pub fn unlock(file: &File) -> Result<()> {
+        let handle = file.as_raw_handle() as isize;
+        if unsafe { UnlockFile(handle, 0, 0, u32::MAX, u32::MAX) } != 0 {
+            Ok(())
+        } else {
+            Err(Error::last_os_error())
+        }
+    }
+}
+
+//=============================================================================
+// FileLock
+//-----------------------------------------------------------------------------
+/// An RAII guard holding an OS advisory lock (`flock` on Unix, `LockFileEx` on
+/// Windows) on a single file, returned by [`super::TestDirUtils::lock_test_file()`].
+///
+/// The lock is released automatically when this guard is dropped.
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Opens (creating it if necessary) and takes an exclusive advisory lock on
+    /// `path`, blocking until the lock is available.
+    pub(crate) fn acquire(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        imp::lock_exclusive(&file)?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // Best-effort: the lock is released by the OS when the file handle is closed
+        // regardless, so a failure here is not worth panicking over.
+        let _ = imp::unlock(&self.file);
+    }
+}
@@ -31,13 +31,58 @@
  */
 //! This module contains utilities that helps the usage of a test directory by
 //! unit-tests.
+mod compression;
+mod filelock;
 #[cfg(test)]
 mod tests;
 
+pub use compression::CompressionFormat;
+pub use filelock::FileLock;
+
+use std::collections::hash_map::RandomState;
 use std::ffi::OsString;
-use std::fs::{create_dir_all, read, read_dir, remove_dir_all, remove_file, write};
-use std::io::Result;
-use std::path::Path;
+use std::fs::{create_dir, create_dir_all, read, read_dir, remove_dir, remove_file, symlink_metadata, write};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Number of attempts made to remove a stubborn file or directory entry before giving up.
+const REMOVE_RETRY_COUNT: u32 = 5;
+
+/// Delay between successive removal attempts.
+const REMOVE_RETRY_DELAY: Duration = Duration::from_millis(10);
+
+/// Maximum number of attempts made to pick an unused unique test directory name before
+/// giving up.
+const UNIQUE_DIR_MAX_ATTEMPTS: u32 = 64;
+
+/// Monotonically increasing counter mixed into every generated unique name, so that two
+/// names requested back to back in the same process hash different inputs.
+static UNIQUE_NAME_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Draws a fresh pseudo-random 64-bit value, seeded from the OS via
+/// [`RandomState`] and mixed with [`UNIQUE_NAME_COUNTER`].
+fn next_random_suffix() -> u64 {
+    let counter = UNIQUE_NAME_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(counter);
+    hasher.finish()
+}
+
+/// Panics if `path` is a root or a prefix (see [`Path::parent()`]), since such a path
+/// would make callers like [`TestDirUtils::remove_all()`] operate outside of a
+/// dedicated test directory.
+fn assert_not_root_or_prefix(path: &Path) {
+    assert!(
+        path.parent().is_some(),
+        "test_root must not be a root or a prefix: {}",
+        path.display()
+    );
+}
 
 //=============================================================================
 // TestDirUtils
@@ -63,9 +108,9 @@ impl TestDirUtils {
     pub const DEFAULT_TEST_DIR: &'static str = "test_dir.tmp";
 
     /// Creates a new `TestDirUtils` with the default name.
-    /// It will automatically create the test directory if it does not exist.
-    /// If the default path points to a file or a symlink, it will be deleted
-    /// and recreated as a directory.
+    /// It will automatically create the test directory if it does not exist,
+    /// along with a fresh, uniquely-named subdirectory for this instance (see
+    /// [`TestDirUtils::with_root()`]).
     ///
     /// Returns the new instance of an error if the test directory is invalid
     /// or cannot be created.
@@ -73,28 +118,48 @@ impl TestDirUtils {
         Self::with_root(Path::new(Self::DEFAULT_TEST_DIR), name)
     }
 
+    /// Creates a new `TestDirUtils` rooted inside the platform's temporary directory
+    /// (as resolved by [`std::env::temp_dir()`]) instead of the fixed
+    /// [`TestDirUtils::DEFAULT_TEST_DIR`] under the crate root.
+    ///
+    /// `std::env::temp_dir()` already honors `TMPDIR` and the equivalent
+    /// platform-specific overrides, so CI systems that mount a tmpfs or a scratch
+    /// directory are respected and fixtures never accidentally land inside the
+    /// repository working tree.
+    ///
+    /// As with [`TestDirUtils::with_root()`], this panics if the resolved temp path
+    /// is a root or a prefix.
+    ///
+    /// Returns the new instance of an error if the test directory is invalid
+    /// or cannot be created.
+    pub fn in_temp_dir(name: &str) -> Result<Self> {
+        Self::with_root(std::env::temp_dir().as_path(), name)
+    }
+
     /// Creates a new `TestDirUtils`. It will automatically create
-    /// the test directory if it does not exist. If the path points to a file or a
-    /// symlink, it will be deleted and recreated as a directory.
+    /// `test_root` if it does not exist, then create a fresh, uniquely-named
+    /// subdirectory of it for this instance to use.
     ///
     /// As a safeguard, this constructor will panic if `test_dir` points to a root
     /// or a prefix (see [`std::path::Path::parent()`] for further details about how
     /// the root is detected).
     ///
+    /// The per-instance subdirectory is created atomically under `test_root`: its name
+    /// is derived from the process id and a random suffix rather than just the current
+    /// thread id, so concurrent processes (or retried tests on the same thread) never
+    /// collide or stomp on each other's fixtures. If a candidate name happens to be
+    /// taken, a new random suffix is drawn and creation is retried; nothing at the
+    /// colliding path is ever deleted or reused.
+    ///
     /// Arguments:
     /// - `test_dir`: The path to the test directory;
     ///
     /// Returns the new instance of an error if the test directory is invalid
     /// or cannot be created.
     pub fn with_root(test_root: &Path, name: &str) -> Result<Self> {
-        let unique_test_dir = Self::create_unique_name_for_thread(name);
-        let full_path = test_root.join(Path::new(&unique_test_dir));
-        if full_path.is_file() {
-            remove_file(full_path.as_path())?;
-        }
-        if !full_path.exists() {
-            create_dir_all(full_path.as_path())?;
-        }
+        assert_not_root_or_prefix(test_root);
+        create_dir_all(test_root)?;
+        let full_path = Self::create_unique_dir(test_root, name)?;
         Ok(Self {
             test_dir: full_path.into_os_string(),
             delete_on_terminate: true,
@@ -115,8 +180,31 @@ impl TestDirUtils {
         self.delete_on_terminate = delete_on_terminate;
     }
 
-    fn create_unique_name_for_thread(name: &str) -> String {
-        format!("{}-{:?}", name, std::thread::current().id())
+    /// Creates a subdirectory of `test_root` whose name is guaranteed to be unused,
+    /// using [`create_dir`] (rather than [`create_dir_all`]) so that the creation is
+    /// atomic: the OS rejects it with [`ErrorKind::AlreadyExists`] instead of letting
+    /// two callers silently share the same directory.
+    ///
+    /// Candidate names are built as `{name}-{pid}-{random}`. On a collision, a new
+    /// random suffix is drawn and the attempt is retried up to
+    /// [`UNIQUE_DIR_MAX_ATTEMPTS`] times.
+    fn create_unique_dir(test_root: &Path, name: &str) -> Result<PathBuf> {
+        for _ in 0..UNIQUE_DIR_MAX_ATTEMPTS {
+            let candidate = test_root.join(format!("{}-{}-{:016x}", name, process::id(), next_random_suffix()));
+            match create_dir(&candidate) {
+                Ok(()) => return Ok(candidate),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!(
+                "unable to find an unused test directory name under '{}' after {} attempts",
+                test_root.display(),
+                UNIQUE_DIR_MAX_ATTEMPTS
+            ),
+        ))
     }
 
     /// Returns the path of the test directory.
@@ -125,23 +213,35 @@ impl TestDirUtils {
     }
 
     /// Deletes all of the contents of the test directory without removing it.
+    ///
+    /// Unlike a plain [`std::fs::remove_dir_all()`], this is robust against read-only
+    /// entries and the kind of transient errors that Windows can raise while a handle
+    /// is still settling: entries are walked bottom-up and, on a failed removal, have
+    /// their read-only bit cleared before a short, bounded sequence of retries.
     pub fn reset(&self) -> Result<()> {
         for entry in read_dir(self.test_dir())? {
-            match entry {
-                Ok(e) => {
-                    let file_type = e.file_type()?;
-                    if file_type.is_file() || file_type.is_symlink() {
-                        remove_file(e.path())?;
-                    } else if file_type.is_dir() {
-                        remove_dir_all(e.path())?;
-                    }
-                }
-                Err(e) => return Err(e),
-            }
+            remove_all_recursive(&entry?.path())?;
         }
         Ok(())
     }
 
+    /// Recursively removes the whole test directory, tolerating read-only entries and
+    /// transient filesystem errors.
+    ///
+    /// This is what [`Drop`] calls on this instance. It never panics on failure; use
+    /// [`TestDirUtils::try_drop()`] if you need to observe the error yourself.
+    pub fn remove_all(&self) -> Result<()> {
+        remove_all_recursive(self.test_dir())
+    }
+
+    /// Attempts to remove the test directory, returning any error instead of only
+    /// logging it to stderr the way [`Drop`] does.
+    ///
+    /// This is useful when a test wants to assert that cleanup actually succeeded.
+    pub fn try_drop(&self) -> Result<()> {
+        self.remove_all()
+    }
+
     /// Get the path of a file inside the test directory.
     pub fn get_test_file_path(&self, name: &str) -> OsString {
         let path = Path::new(&self.test_dir);
@@ -187,6 +287,104 @@ impl TestDirUtils {
         Ok(read(p)?)
     }
 
+    /// Compresses `contents` with `format` and creates a test file with the
+    /// specified name holding the compressed bytes.
+    ///
+    /// This keeps large binary test vectors (e.g. IL2 serialization fixtures) out of
+    /// the repository as raw bytes. Use [`TestDirUtils::read_compressed_test_file()`]
+    /// to read it back; [`TestDirUtils::read_test_file()`] keeps working on plain,
+    /// uncompressed files.
+    ///
+    /// Arguments:
+    /// - `name`: The name of the file to be created;
+    /// - `contents`: The uncompressed contents of the file;
+    /// - `format`: The compression format to use;
+    ///
+    /// Returns the path to the newly created file.
+    pub fn create_compressed_test_file(&self, name: &str, contents: &[u8], format: CompressionFormat) -> Result<OsString> {
+        let compressed = format.compress(contents)?;
+        self.create_test_file(name, &compressed)
+    }
+
+    /// Reads the specified test file and transparently decompresses it.
+    ///
+    /// The format is detected from the file extension (`.gz`, `.xz`, `.zst`) and,
+    /// failing that, from the magic bytes at the start of the file.
+    ///
+    /// Arguments:
+    /// - `name`: The name of the file to be read;
+    ///
+    /// Returns the decompressed contents of the file.
+    pub fn read_compressed_test_file(&self, name: &str) -> Result<Vec<u8>> {
+        let raw = self.read_test_file(name)?;
+        let format = CompressionFormat::from_extension(Path::new(name))
+            .or_else(|| CompressionFormat::sniff(&raw))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("unable to determine the compression format of '{}'", name)))?;
+        format.decompress(&raw)
+    }
+
+    /// Takes an OS advisory lock (`flock` on Unix, `LockFileEx` on Windows) on the
+    /// test file `name`, blocking until it is available, and returns an RAII guard
+    /// that releases it on drop.
+    ///
+    /// This is meant for fixtures shared across test *processes* rather than the
+    /// per-instance test directory: it lets a test serialize its access to such a
+    /// fixture while the rest of the suite keeps running in parallel.
+    ///
+    /// `name` is resolved like [`TestDirUtils::get_test_file_path()`]: a relative name
+    /// points inside this instance's own unique directory, which no other process
+    /// uses, while an absolute path replaces the test directory and is locked as is.
+    ///
+    /// Arguments:
+    /// - `name`: The name of the file to lock;
+    pub fn lock_test_file(&self, name: &str) -> Result<FileLock> {
+        let full_path = self.get_test_file_path(name);
+        FileLock::acquire(Path::new(&full_path))
+    }
+
+    /// Name of the environment variable that, when set to "1", makes
+    /// [`TestDirUtils::assert_test_file_eq()`] "bless" a mismatching golden file
+    /// instead of panicking: the standard snapshot-testing update workflow.
+    pub const UPDATE_FIXTURES_ENV_VAR: &'static str = "UPDATE_FIXTURES";
+
+    /// Asserts that the contents of the test file `name` equal `expected`, the way a
+    /// golden-file (snapshot) test does.
+    ///
+    /// On a mismatch, this panics with a human-readable line diff (see [`diff_text()`])
+    /// instead of the raw byte dump that a plain `assert_eq!` would produce.
+    ///
+    /// If the [`TestDirUtils::UPDATE_FIXTURES_ENV_VAR`] environment variable is set to
+    /// "1", a mismatch does not panic: the test file is written (or, for a golden
+    /// file that does not exist yet, first created) with `expected` via
+    /// [`TestDirUtils::create_test_file()`] instead, so that running the test once
+    /// with `UPDATE_FIXTURES=1` "blesses" the golden contents, including the very
+    /// first run of a brand-new golden test.
+    ///
+    /// Arguments:
+    /// - `name`: The name of the golden test file;
+    /// - `expected`: The contents the test file is expected to have;
+    pub fn assert_test_file_eq(&self, name: &str, expected: &[u8]) -> Result<()> {
+        let existing = match self.read_test_file(name) {
+            Ok(actual) => Some(actual),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        if existing.as_deref() == Some(expected) {
+            return Ok(());
+        }
+        if std::env::var(Self::UPDATE_FIXTURES_ENV_VAR).as_deref() == Ok("1") {
+            self.create_test_file(name, expected)?;
+            return Ok(());
+        }
+        let actual = existing.unwrap_or_default();
+        panic!(
+            "test file '{}' does not match the expected contents (set {}=1 to update it):\n{}",
+            name,
+            Self::UPDATE_FIXTURES_ENV_VAR,
+            diff_text(&String::from_utf8_lossy(expected), &String::from_utf8_lossy(&actual))
+        );
+    }
+
     /// Deletes the specified file.
     ///
     /// This method does nothing if the test file does not exist.
@@ -207,7 +405,180 @@ impl TestDirUtils {
 impl Drop for TestDirUtils {
     fn drop(&mut self) {
         if self.delete_on_terminate {
-            remove_dir_all(Path::new(self.test_dir())).unwrap();
+            if let Err(e) = self.remove_all() {
+                // Dropping during unwinding must never panic (that would abort the
+                // process), and a locked/read-only leftover is not worth failing the
+                // test over, so this is logged rather than propagated.
+                eprintln!(
+                    "TestDirUtils: unable to remove test directory '{}': {}",
+                    self.test_dir().display(),
+                    e
+                );
+            }
         }
     }
 }
+
+//=============================================================================
+// Recursive, retrying removal
+//-----------------------------------------------------------------------------
+/// Clears the read-only bit of `path`, if set. Best-effort: errors are ignored by the
+/// caller since the subsequent removal attempt will surface anything that still fails.
+///
+/// On Unix, this adds only the owner-write bit rather than going through
+/// [`std::fs::Permissions::set_readonly()`], which would also grant group/other write
+/// access — more than a retried delete needs.
+#[cfg(unix)]
+fn clear_readonly(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = symlink_metadata(path)?;
+    let mut permissions = metadata.permissions();
+    let mode = permissions.mode();
+    if mode & 0o200 == 0 {
+        permissions.set_mode(mode | 0o200);
+        std::fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+/// Clears the read-only bit of `path`, if set. Best-effort: errors are ignored by the
+/// caller since the subsequent removal attempt will surface anything that still fails.
+#[cfg(not(unix))]
+fn clear_readonly(path: &Path) -> Result<()> {
+    let metadata = symlink_metadata(path)?;
+    let mut permissions = metadata.permissions();
+    if permissions.readonly() {
+        permissions.set_readonly(false);
+        std::fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+/// Runs `remove` against `path`, making up to [`REMOVE_RETRY_COUNT`] attempts in total
+/// with a short backoff between them. After each failed attempt, the read-only bit is
+/// cleared, which is what makes
+/// removal of fixtures written with restricted permissions (or still settling on
+/// Windows) succeed instead of failing outright.
+fn remove_with_retry(path: &Path, remove: fn(&Path) -> Result<()>) -> Result<()> {
+    let mut last_err = None;
+    for attempt in 0..REMOVE_RETRY_COUNT {
+        match remove(path) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                let _ = clear_readonly(path);
+                last_err = Some(e);
+                if attempt + 1 < REMOVE_RETRY_COUNT {
+                    sleep(REMOVE_RETRY_DELAY);
+                }
+            }
+        }
+    }
+    Err(last_err.expect("at least one removal attempt must have been made"))
+}
+
+/// Recursively removes `path`, whether it is a file, a symlink or a directory.
+///
+/// Directories are walked bottom-up: their entries are removed first and the now-empty
+/// directory is removed last. Directory symlinks are never followed, they are simply
+/// unlinked like any other file, which keeps the walk safe from symlink cycles that
+/// escape the test directory.
+///
+/// A `path` that is already gone is treated as success, the same way
+/// [`TestDirUtils::delete_test_file()`] does, so calling
+/// [`TestDirUtils::try_drop()`] ahead of the struct's normal `Drop` never produces a
+/// spurious "not found" error (or, from `Drop`, a spurious log line) on the second
+/// removal.
+fn remove_all_recursive(path: &Path) -> Result<()> {
+    let metadata = match symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if metadata.file_type().is_dir() {
+        for entry in read_dir(path)? {
+            remove_all_recursive(&entry?.path())?;
+        }
+        remove_with_retry(path, |p| remove_dir(p))
+    } else {
+        remove_with_retry(path, |p| remove_file(p))
+    }
+}
+
+//=============================================================================
+// Golden-file diff
+//-----------------------------------------------------------------------------
+/// How a line of `expected` or `actual` participates in the diff produced by
+/// [`diff_lines()`].
+enum DiffLine {
+    /// The line is common to both texts, carrying its index into `expected`.
+    Context(usize),
+    /// The line only appears in `expected`, carrying its index into `expected`.
+    Removed(usize),
+    /// The line only appears in `actual`, carrying its index into `actual`.
+    Added(usize),
+}
+
+/// Builds the longest-common-subsequence length table between `expected` and `actual`,
+/// the classic DP used by line-level diff tools.
+fn lcs_table(expected: &[&str], actual: &[&str]) -> Vec<Vec<u32>> {
+    let mut table = vec![vec![0u32; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            table[i][j] = if expected[i] == actual[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+    table
+}
+
+/// Walks the LCS table computed by [`lcs_table()`] to produce the sequence of
+/// [`DiffLine`] operations that turns `expected` into `actual`.
+fn diff_lines(expected: &[&str], actual: &[&str]) -> Vec<DiffLine> {
+    let table = lcs_table(expected, actual);
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() && j < actual.len() {
+        if expected[i] == actual[j] {
+            ops.push(DiffLine::Context(i));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            ops.push(DiffLine::Removed(i));
+            i += 1;
+        } else {
+            ops.push(DiffLine::Added(j));
+            j += 1;
+        }
+    }
+    ops.extend((i..expected.len()).map(DiffLine::Removed));
+    ops.extend((j..actual.len()).map(DiffLine::Added));
+    ops
+}
+
+/// Computes a human-readable, unified-diff-style line comparison between `expected`
+/// and `actual`.
+///
+/// Common lines are rendered with a leading `  ` (context), lines only in `expected`
+/// with a leading `- `, and lines only in `actual` with a leading `+ `. Every line of
+/// both texts is rendered as one single block: unlike `diff -u`, there are no `@@`
+/// hunk headers and unchanged lines are not trimmed.
+pub fn diff_text(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let mut rendered = String::new();
+    for op in diff_lines(&expected_lines, &actual_lines) {
+        let (prefix, line) = match op {
+            DiffLine::Context(i) => ("  ", expected_lines[i]),
+            DiffLine::Removed(i) => ("- ", expected_lines[i]),
+            DiffLine::Added(j) => ("+ ", actual_lines[j]),
+        };
+        rendered.push_str(prefix);
+        rendered.push_str(line);
+        rendered.push('\n');
+    }
+    rendered
+}
@@ -42,8 +42,13 @@ fn test_testdirutils_new() {
     let curr_path = OsString::from(test_dir.test_dir());
     drop(test_dir);
     assert!(Path::new(&curr_path).exists());
-    // Create normal
+    std::fs::remove_dir_all(Path::new(&curr_path)).unwrap();
+
+    // Create another normal instance. Since every instance now gets its own
+    // PID+random unique directory (see chunk0-2), this is a distinct path from
+    // `curr_path` above, even with the same `name`.
     let test_dir = TestDirUtils::new("test_testdirutils_new").unwrap();
+    assert_ne!(OsString::from(test_dir.test_dir()), curr_path);
     let contents = b"this is just a test!";
     test_dir.create_test_file("test", contents).unwrap();
     let actual = test_dir.read_test_file("test").unwrap();
@@ -52,6 +57,170 @@ fn test_testdirutils_new() {
     test_dir.delete_test_file("test").unwrap();
     let test_file = test_dir.get_test_file_path("test");
     assert!(!Path::new(&test_file).exists());
+    let second_path = OsString::from(test_dir.test_dir());
     drop(test_dir);
+    assert!(!Path::new(&second_path).exists());
+}
+
+#[test]
+fn test_testdirutils_remove_all_with_readonly_file() {
+    let test_dir = TestDirUtils::new("test_testdirutils_remove_all_with_readonly_file").unwrap();
+    let path = test_dir.create_test_file("readonly", b"cannot touch this").unwrap();
+    let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&path, permissions).unwrap();
+
+    // remove_all() must clear the read-only bit instead of failing.
+    test_dir.remove_all().unwrap();
+    assert!(!Path::new(&path).exists());
+}
+
+#[test]
+fn test_testdirutils_remove_all_twice_is_ok() {
+    let test_dir = TestDirUtils::new("test_testdirutils_remove_all_twice_is_ok").unwrap();
+    test_dir.remove_all().unwrap();
+    // A second call, with the directory already gone, must succeed rather than
+    // surface a "not found" error.
+    test_dir.remove_all().unwrap();
+}
+
+#[test]
+fn test_testdirutils_try_drop() {
+    let test_dir = TestDirUtils::new("test_testdirutils_try_drop").unwrap();
+    let curr_path = OsString::from(test_dir.test_dir());
+    test_dir.try_drop().unwrap();
     assert!(!Path::new(&curr_path).exists());
+    // A second try_drop() on an already-removed directory is a no-op, not an error:
+    // this is what makes calling it ahead of the struct's normal Drop safe.
+    test_dir.try_drop().unwrap();
+}
+
+#[test]
+fn test_testdirutils_in_temp_dir() {
+    let test_dir = TestDirUtils::in_temp_dir("test_testdirutils_in_temp_dir").unwrap();
+    assert!(test_dir.test_dir().starts_with(std::env::temp_dir()));
+    let contents = b"stored outside of the repository";
+    test_dir.create_test_file("test", contents).unwrap();
+    assert_eq!(test_dir.read_test_file("test").unwrap().as_slice(), contents);
+}
+
+#[test]
+fn test_testdirutils_with_root_unique_names_dont_collide() {
+    let mut paths = std::collections::HashSet::new();
+    let mut dirs = Vec::new();
+    for _ in 0..8 {
+        let test_dir = TestDirUtils::new("test_testdirutils_with_root_unique_names_dont_collide").unwrap();
+        assert!(paths.insert(OsString::from(test_dir.test_dir())));
+        dirs.push(test_dir);
+    }
+}
+
+#[test]
+fn test_testdirutils_compressed_test_file_gz() {
+    let test_dir = TestDirUtils::new("test_testdirutils_compressed_test_file_gz").unwrap();
+    let contents = b"this is just a test!".repeat(64);
+    test_dir
+        .create_compressed_test_file("fixture.bin.gz", &contents, CompressionFormat::Gz)
+        .unwrap();
+    let actual = test_dir.read_compressed_test_file("fixture.bin.gz").unwrap();
+    assert_eq!(actual, contents);
+}
+
+#[test]
+fn test_testdirutils_compressed_test_file_xz() {
+    let test_dir = TestDirUtils::new("test_testdirutils_compressed_test_file_xz").unwrap();
+    let contents = b"this is just a test!".repeat(64);
+    test_dir
+        .create_compressed_test_file("fixture.bin.xz", &contents, CompressionFormat::Xz)
+        .unwrap();
+    let actual = test_dir.read_compressed_test_file("fixture.bin.xz").unwrap();
+    assert_eq!(actual, contents);
+}
+
+#[test]
+fn test_testdirutils_compressed_test_file_zstd() {
+    let test_dir = TestDirUtils::new("test_testdirutils_compressed_test_file_zstd").unwrap();
+    let contents = b"this is just a test!".repeat(64);
+    test_dir
+        .create_compressed_test_file("fixture.bin.zst", &contents, CompressionFormat::Zstd)
+        .unwrap();
+    let actual = test_dir.read_compressed_test_file("fixture.bin.zst").unwrap();
+    assert_eq!(actual, contents);
+}
+
+#[test]
+fn test_testdirutils_lock_test_file() {
+    let test_dir = TestDirUtils::new("test_testdirutils_lock_test_file").unwrap();
+    // Acquiring and releasing the lock must not fail, and must create the file if
+    // it does not exist yet.
+    let lock = test_dir.lock_test_file("shared.lock").unwrap();
+    drop(lock);
+    let path = test_dir.get_test_file_path("shared.lock");
+    assert!(Path::new(&path).exists());
+    // The lock must be re-acquirable once released.
+    test_dir.lock_test_file("shared.lock").unwrap();
+}
+
+//=============================================================================
+// Golden-file diff
+//-----------------------------------------------------------------------------
+#[test]
+fn test_diff_text_identical() {
+    assert_eq!(diff_text("a\nb\nc", "a\nb\nc"), "  a\n  b\n  c\n");
+}
+
+#[test]
+fn test_diff_text_mismatch() {
+    let diff = diff_text("a\nb\nc", "a\nx\nc");
+    assert_eq!(diff, "  a\n- b\n+ x\n  c\n");
+}
+
+/// Serializes the tests that read or mutate the process-wide `UPDATE_FIXTURES`
+/// environment variable, since `cargo test` runs tests in parallel threads of the
+/// same process and `std::env::set_var`/`remove_var` are not scoped per-thread.
+static UPDATE_FIXTURES_ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[test]
+fn test_testdirutils_assert_test_file_eq() {
+    let _guard = UPDATE_FIXTURES_ENV_LOCK.lock().unwrap();
+    std::env::remove_var(TestDirUtils::UPDATE_FIXTURES_ENV_VAR);
+
+    let test_dir = TestDirUtils::new("test_testdirutils_assert_test_file_eq").unwrap();
+    test_dir.create_test_file("golden", b"line1\nline2\n").unwrap();
+    test_dir.assert_test_file_eq("golden", b"line1\nline2\n").unwrap();
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        test_dir.assert_test_file_eq("golden", b"line1\nline3\n").unwrap();
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_testdirutils_assert_test_file_eq_update_fixtures() {
+    let _guard = UPDATE_FIXTURES_ENV_LOCK.lock().unwrap();
+
+    let test_dir = TestDirUtils::new("test_testdirutils_assert_test_file_eq_update_fixtures").unwrap();
+    test_dir.create_test_file("golden", b"old\n").unwrap();
+
+    std::env::set_var(TestDirUtils::UPDATE_FIXTURES_ENV_VAR, "1");
+    let result = test_dir.assert_test_file_eq("golden", b"new\n");
+    std::env::remove_var(TestDirUtils::UPDATE_FIXTURES_ENV_VAR);
+
+    result.unwrap();
+    assert_eq!(test_dir.read_test_file("golden").unwrap(), b"new\n");
+}
+
+#[test]
+fn test_testdirutils_assert_test_file_eq_update_fixtures_creates_missing_golden() {
+    let _guard = UPDATE_FIXTURES_ENV_LOCK.lock().unwrap();
+
+    let test_dir = TestDirUtils::new("test_testdirutils_assert_test_file_eq_update_fixtures_creates_missing_golden").unwrap();
+    // No golden file exists yet: UPDATE_FIXTURES=1 must create it instead of
+    // propagating the NotFound error from reading it, the usual first-run workflow.
+    std::env::set_var(TestDirUtils::UPDATE_FIXTURES_ENV_VAR, "1");
+    let result = test_dir.assert_test_file_eq("golden", b"first run\n");
+    std::env::remove_var(TestDirUtils::UPDATE_FIXTURES_ENV_VAR);
+
+    result.unwrap();
+    assert_eq!(test_dir.read_test_file("golden").unwrap(), b"first run\n");
 }